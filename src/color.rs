@@ -0,0 +1,81 @@
+//! Maps raw iteration counts to RGB pixels so callers get a displayable
+//! image straight out of Rust instead of doing the palette lookup in
+//! Python.
+
+/// Control-point palette used by [`colorize`], lifted from the classic
+/// SIMD Mandelbrot gradient (dark blue -> cyan -> white -> orange ->
+/// black).
+pub const DEFAULT_PALETTE: [(u8, u8, u8); 5] = [
+    (0, 7, 100),
+    (32, 107, 203),
+    (237, 255, 255),
+    (255, 170, 0),
+    (0, 2, 0),
+];
+
+/// Maps a slice of iteration counts to interleaved `(r, g, b)` bytes by
+/// cycling through `palette` every `scale` iterations and linearly
+/// interpolating between the two nearest control points. Pixels where
+/// `count == iter_limit` (i.e. they never escaped) are painted black.
+pub fn colorize(counts: &[u32], iter_limit: u32, scale: f32, palette: &[(u8, u8, u8)]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(counts.len() * 3);
+
+    for &count in counts {
+        if count >= iter_limit {
+            out.extend_from_slice(&[0, 0, 0]);
+            continue;
+        }
+
+        let v = (count as f32 % scale) * (palette.len() as f32 / scale);
+        let left = v.floor() as usize % palette.len();
+        let right = (left + 1) % palette.len();
+        let frac = v - v.floor();
+
+        let (lr, lg, lb) = palette[left];
+        let (rr, rg, rb) = palette[right];
+        out.push(lerp_u8(lr, rr, frac));
+        out.push(lerp_u8(lg, rg, frac));
+        out.push(lerp_u8(lb, rb, frac));
+    }
+
+    out
+}
+
+#[inline]
+fn lerp_u8(a: u8, b: u8, frac: f32) -> u8 {
+    (a as f32 + (b as f32 - a as f32) * frac).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn never_escaped_pixels_are_black() {
+        let counts = [0, 50, 100];
+        let rgb = colorize(&counts, 100, 20.0, &DEFAULT_PALETTE);
+        assert_eq!(&rgb[6..9], &[0, 0, 0]);
+    }
+
+    #[test]
+    fn escaped_pixel_lands_on_a_palette_control_point() {
+        // count == 0 always falls exactly on the first control point,
+        // regardless of scale.
+        let rgb = colorize(&[0], 100, 20.0, &DEFAULT_PALETTE);
+        assert_eq!(&rgb[..3], &[DEFAULT_PALETTE[0].0, DEFAULT_PALETTE[0].1, DEFAULT_PALETTE[0].2]);
+    }
+
+    #[test]
+    fn palette_cycles_with_scale() {
+        // count == scale should map back to the same color as count == 0.
+        let rgb = colorize(&[0, 20], 100, 20.0, &DEFAULT_PALETTE);
+        assert_eq!(&rgb[0..3], &rgb[3..6]);
+    }
+
+    #[test]
+    fn output_is_three_bytes_per_pixel() {
+        let counts = [0, 1, 2, 3, 4];
+        let rgb = colorize(&counts, 100, 20.0, &DEFAULT_PALETTE);
+        assert_eq!(rgb.len(), counts.len() * 3);
+    }
+}
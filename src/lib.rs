@@ -3,13 +3,22 @@ use ndarray::{Dim, ArrayViewMut, IxDynImpl};
 use numpy::{PyArray, PyArrayDyn, IntoPyArray};
 use rayon::prelude::*;
 
+mod color;
 mod simd_par;
+mod simd_par_f64;
+mod tiling;
+
+use tiling::{tiles, TileBuf};
 
 #[pymodule]
 fn mandelbrot(_py: Python, m: &PyModule) -> PyResult<()> {
     m.add_function(wrap_pyfunction!(compute_mandelbrot_rs, m)?)?;
     m.add_function(wrap_pyfunction!(compute_mandelbrot_rs_par, m)?)?;
     m.add_function(wrap_pyfunction!(compute_mandelbrot_rs_simd_par, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_mandelbrot_rs_simd_par_smooth, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_mandelbrot_rs_simd_par_f64, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_mandelbrot_rs_rgb, m)?)?;
+    m.add_function(wrap_pyfunction!(compute_julia_rs_simd_par, m)?)?;
     Ok(())
 }
 
@@ -55,7 +64,95 @@ fn compute_mandelbrot_rs_simd_par<'py>(
     height: usize, 
     iters: u32,
 ) -> &'py PyArray<u32, Dim<[usize; 2]>> {
-    let out = simd_par::generate(min_x, max_x, min_y, max_y, width, height, iters);
+    let out = simd_par::generate(min_x as f32, max_x as f32, min_y as f32, max_y as f32, width, height, iters);
+    out.into_pyarray(py).reshape([height as usize, width as usize]).unwrap()
+}
+
+/// Smooth-coloring variant of `compute_mandelbrot_rs_simd_par`: returns the
+/// continuous (normalized iteration count) escape value per pixel instead
+/// of the raw iteration count, so the Python side can render without
+/// banding.
+#[pyfunction]
+fn compute_mandelbrot_rs_simd_par_smooth<'py>(
+    py: Python<'py>,
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+    width: usize,
+    height: usize,
+    iters: u32,
+) -> &'py PyArray<f32, Dim<[usize; 2]>> {
+    let out = simd_par::generate_smooth(min_x as f32, max_x as f32, min_y as f32, max_y as f32, width, height, iters);
+    out.into_pyarray(py).reshape([height as usize, width as usize]).unwrap()
+}
+
+/// Full `f64`-precision counterpart to `compute_mandelbrot_rs_simd_par`.
+/// Slower (half the SIMD lanes per instruction) but keeps the mantissa
+/// bits the `f32` fast path throws away, so deep zooms don't pixelate.
+#[pyfunction]
+fn compute_mandelbrot_rs_simd_par_f64<'py>(
+    py: Python<'py>,
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+    width: usize,
+    height: usize,
+    iters: u32,
+) -> &'py PyArray<u32, Dim<[usize; 2]>> {
+    let out = simd_par_f64::generate_f64(min_x, max_x, min_y, max_y, width, height, iters);
+    out.into_pyarray(py).reshape([height as usize, width as usize]).unwrap()
+}
+
+/// Computes the Mandelbrot set like `compute_mandelbrot_rs_simd_par`, but
+/// returns a directly displayable `(height, width, 3)` RGB image instead
+/// of raw iteration counts, using the built-in gradient palette.
+#[pyfunction]
+fn compute_mandelbrot_rs_rgb<'py>(
+    py: Python<'py>,
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+    width: usize,
+    height: usize,
+    iters: u32,
+    scale: f32,
+) -> &'py PyArray<u8, Dim<[usize; 3]>> {
+    let counts = simd_par::generate(min_x as f32, max_x as f32, min_y as f32, max_y as f32, width, height, iters);
+    let rgb = color::colorize(&counts, iters, scale, &color::DEFAULT_PALETTE);
+    rgb.into_pyarray(py).reshape([height, width, 3]).unwrap()
+}
+
+/// Computes a Julia set for the fixed constant `(c_re, c_im)` over the
+/// given bounds, using the same SIMD divergence machinery as
+/// `compute_mandelbrot_rs_simd_par` -- the pixel grid supplies `z0`
+/// instead of `c`.
+#[pyfunction]
+fn compute_julia_rs_simd_par<'py>(
+    py: Python<'py>,
+    c_re: f64,
+    c_im: f64,
+    min_x: f64,
+    max_x: f64,
+    min_y: f64,
+    max_y: f64,
+    width: usize,
+    height: usize,
+    iters: u32,
+) -> &'py PyArray<u32, Dim<[usize; 2]>> {
+    let out = simd_par::generate_julia(
+        c_re as f32,
+        c_im as f32,
+        min_x as f32,
+        max_x as f32,
+        min_y as f32,
+        max_y as f32,
+        width,
+        height,
+        iters,
+    );
     out.into_pyarray(py).reshape([height as usize, width as usize]).unwrap()
 }
 
@@ -94,9 +191,27 @@ impl Complex {
     }
 }
 
-fn mandelbrot_kernel(x: f32, y: f32, max: u8) -> u8 {
-    let mut z = Complex { a: 0.0, b: 0.0 };
-    let c = Complex { a: x, b: y };
+/// Iterates `z = z*z + c` starting from `z0`, up to `max` times. Passing
+/// `z0 == c` gives the classic Mandelbrot map; holding `c` fixed and
+/// varying `z0` instead gives the Julia set for that `c`.
+fn mandelbrot_kernel(z0_x: f32, z0_y: f32, c_x: f32, c_y: f32, max: u8) -> u8 {
+    // Most of the black interior never escapes, so test membership in the
+    // main cardioid and the period-2 bulb up front and skip the iteration
+    // entirely for points inside either one. This short-circuit only holds
+    // for the Mandelbrot map itself (z0 == c) -- it says nothing about the
+    // orbit of an arbitrary Julia z0.
+    if z0_x == c_x && z0_y == c_y {
+        let q = (c_x - 0.25) * (c_x - 0.25) + c_y * c_y;
+        if q * (q + (c_x - 0.25)) <= 0.25 * c_y * c_y {
+            return max;
+        }
+        if (c_x + 1.0) * (c_x + 1.0) + c_y * c_y <= 0.0625 {
+            return max;
+        }
+    }
+
+    let mut z = Complex { a: z0_x, b: z0_y };
+    let c = Complex { a: c_x, b: c_y };
     let mut i = 0u8;
     while i < max && z.arg_sq() < 4.0 {
         z = z * z + c;
@@ -111,16 +226,21 @@ fn compute_mandelbrot(min_x: f32, max_x: f32, min_y: f32, max_y: f32, width: u32
     let mut y = min_y;
     for row in 0..height {
         let mut x = min_x;
-        for col in 0..height{
-            t[[row as usize, col as usize]] = mandelbrot_kernel(x, y, iters);
+        for col in 0..width {
+            t[[row as usize, col as usize]] = mandelbrot_kernel(x, y, x, y, iters);
             x += dx;
         }
         y += dy;
     }
 }
 
+// Tile size for `compute_mandelbrot_par`, in pixels.
+const TILE_ROWS: usize = 32;
+const TILE_COLS: usize = 256;
+
 fn compute_mandelbrot_par(min_x: f32, max_x: f32, min_y: f32, max_y: f32, width: u32, height: u32, iters: u8) -> Vec<u8> {
-    let len = (width * height) as usize;
+    let (width, height) = (width as usize, height as usize);
+    let len = width * height;
     let mut out = Vec::with_capacity(len);
     unsafe {
         out.set_len(len);
@@ -129,12 +249,21 @@ fn compute_mandelbrot_par(min_x: f32, max_x: f32, min_y: f32, max_y: f32, width:
     let dx = (max_x - min_x) / width as f32;
     let dy = (max_y - min_y) / height as f32;
 
-    out.par_chunks_mut(width as usize).enumerate().for_each(|(i, row)| {
-        let y = min_y + dy * i as f32;
-        row.iter_mut().enumerate().for_each(|(j, col)| {
-            let x = min_x + dx * j as f32;
-            *col = mandelbrot_kernel(x, y, iters);
-        });
+    let buf = TileBuf(out.as_mut_ptr());
+    tiles(height, width, TILE_ROWS, TILE_COLS).into_par_iter().for_each(|(row0, row1, col0, col1)| {
+        let buf = buf;
+        for row in row0..row1 {
+            let y = min_y + dy * row as f32;
+            for col in col0..col1 {
+                let x = min_x + dx * col as f32;
+                let value = mandelbrot_kernel(x, y, x, y, iters);
+                // Safe: tiles partition the image, so each tile owns a
+                // disjoint set of indices and no two tasks write the same one.
+                unsafe {
+                    *buf.0.add(row * width + col) = value;
+                }
+            }
+        }
     });
     out
 }
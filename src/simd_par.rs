@@ -2,8 +2,13 @@
 #![allow(non_camel_case_types)]
 
 use crate::*;
+use crate::tiling::generate_tiled;
 use packed_simd::*;
 
+// Tile size for the parallel generators below, in SIMD blocks.
+const TILE_ROWS: usize = 32;
+const TILE_COLS_BLOCKS: usize = 32;
+
 type u32s = u32x8;
 type f32s = f32x8;
 type m32s = m32x8;
@@ -18,6 +23,13 @@ struct Complex {
 
 const THRESHOLD: f32 = 4.0;
 
+/// Escape radius used only by `count_smooth`. Raised from the textbook 4.0
+/// so the log terms in the continuous escape value stay numerically
+/// stable (a small `sum` at escape makes `ln(ln(sqrt(sum)))` blow up).
+/// Kept separate from `THRESHOLD` so it doesn't change the iteration
+/// counts `count()` reports for the existing entry points.
+const SMOOTH_THRESHOLD: f32 = 256.0;
+
 impl Complex {
     /// Returns a mask describing which members of the Mandelbrot sequence
     /// haven't diverged yet
@@ -31,35 +43,61 @@ impl Complex {
 
         sum.le(f32s::splat(THRESHOLD))
     }
+
+    /// Returns a mask describing which lanes lie in the main cardioid or
+    /// the period-2 bulb, where the sequence is known never to diverge.
+    #[inline]
+    fn interior(&self) -> m32s {
+        let Self { real: x, imag: y } = *self;
+
+        let xq = x - f32s::splat(0.25);
+        let q = xq * xq + y * y;
+        let in_cardioid = (q * (q + xq)).le(f32s::splat(0.25) * y * y);
+
+        let x1 = x + f32s::splat(1.0);
+        let in_bulb = (x1 * x1 + y * y).le(f32s::splat(0.0625));
+
+        in_cardioid | in_bulb
+    }
 }
 
-/// Mandelbrot sequence iterator using SIMD.
+/// Quadratic-map (`z = z*z + c`) sequence iterator using SIMD. Feeding it
+/// `z0 == c` gives the classic Mandelbrot map; holding `c` fixed across
+/// the whole image and varying `z0` per pixel instead gives the Julia set
+/// for that `c`.
 struct MandelbrotIter {
-    /// Initial value which generated this sequence
-    start: Complex,
-    /// Current iteration value
+    /// The constant added on every iteration
+    c: Complex,
+    /// Current iteration value, seeded with `z0`
     current: Complex,
 }
 
 impl MandelbrotIter {
-    /// Creates a new Mandelbrot sequence iterator for a given starting point
-    fn new(start: Complex) -> Self {
-        Self { start, current: start }
+    /// Creates a new sequence iterator starting at `z0` with constant `c`.
+    fn new(z0: Complex, c: Complex) -> Self {
+        Self { c, current: z0 }
     }
 
     /// Returns the number of iterations it takes for each member of the
-    /// Mandelbrot sequence to diverge at this point, or `ITER_LIMIT` if
-    /// they don't diverge.
+    /// sequence to diverge at this point, or `iters` if they don't
+    /// diverge.
+    ///
+    /// `interior` marks lanes already known never to diverge (see
+    /// `Complex::interior`) so the iteration can be skipped for them;
+    /// pass an all-false mask where that test doesn't apply (e.g. Julia
+    /// sets, where it's only valid for `c == z0`).
     ///
     /// This function will operate on N complex numbers at once, where N is the
     /// number of lanes in a SIMD vector of doubles.
-    fn count(mut self, iters: u32) -> u32s {
-        let mut z = self.start;
-        let mut count = u32s::splat(0);
+    fn count(mut self, iters: u32, interior: m32s) -> u32s {
+        let mut z = self.current;
+        let mut count = interior.select(u32s::splat(iters), u32s::splat(0));
+        let active = !interior;
+
         for _ in 0..iters {
             // Keep track of those lanes which haven't diverged yet. The other
             // ones will be masked off.
-            let undiverged = z.undiverged();
+            let undiverged = z.undiverged() & active;
 
             // Stop the iteration if they all diverged. Note that we don't do
             // this check every iteration, since a branch
@@ -75,6 +113,62 @@ impl MandelbrotIter {
         }
         count.cast()
     }
+
+    /// Returns the normalized (continuous) escape value for each member of
+    /// the sequence, i.e. the smooth-coloring `mu` value, or `iters` for
+    /// lanes that never diverge.
+    ///
+    /// Unlike `count`, which increments every lane that hasn't diverged yet
+    /// on every iteration, this latches the escape iteration and the
+    /// escape `sum` for a lane exactly once, at the iteration where it
+    /// transitions from undiverged to diverged.
+    ///
+    /// `interior` marks lanes already known never to diverge (see
+    /// `Complex::interior`), same as `count`; those lanes are excluded from
+    /// the iteration and fall through to the final `iters` value below.
+    fn count_smooth(mut self, iters: u32, interior: m32s) -> f32s {
+        let mut z = self.current;
+        let mut escaped = m32s::splat(false);
+        let mut escaped_n = u32s::splat(iters);
+        let mut escaped_sum = f32s::splat(SMOOTH_THRESHOLD);
+        let active = !interior;
+
+        for n in 0..iters {
+            let Complex { real: x, imag: y } = z;
+            let sum = x * x + y * y;
+            let undiverged = sum.le(f32s::splat(SMOOTH_THRESHOLD));
+            let newly_escaped = !undiverged & !escaped & active;
+
+            if newly_escaped.any() {
+                escaped_n = newly_escaped.select(u32s::splat(n), escaped_n);
+                escaped_sum = newly_escaped.select(sum, escaped_sum);
+            }
+
+            escaped |= newly_escaped;
+            if (escaped | interior).all() {
+                break;
+            }
+
+            z = self.next().unwrap();
+        }
+
+        // mu = n + 1 - ln(ln(sqrt(sum))) / ln(2)
+        let log_term = ln_f32s(ln_f32s(escaped_sum.sqrt())) / f32s::splat(std::f32::consts::LN_2);
+        let mu = escaped_n.cast::<f32s>() + f32s::splat(1.0) - log_term;
+
+        escaped.select(mu, f32s::splat(iters as f32))
+    }
+}
+
+/// Applies `f32::ln` lane-wise. `packed_simd` doesn't provide a vectorized
+/// transcendental, so this round-trips through scalar `ln`.
+#[inline]
+fn ln_f32s(v: f32s) -> f32s {
+    let mut out = f32s::splat(0.0);
+    for i in 0..f32s::lanes() {
+        out = out.replace(i, v.extract(i).ln());
+    }
+    out
 }
 
 impl Iterator for MandelbrotIter {
@@ -83,7 +177,7 @@ impl Iterator for MandelbrotIter {
     /// Generates the next values in the sequence
     #[inline]
     fn next(&mut self) -> Option<Complex> {
-        let Complex { real: c_x, imag: c_y } = self.start;
+        let Complex { real: c_x, imag: c_y } = self.c;
         let Complex { real: x, imag: y } = self.current;
 
         let xx = x * x;
@@ -99,21 +193,10 @@ impl Iterator for MandelbrotIter {
     }
 }
 
-pub fn generate(min_x: f32, max_x: f32, min_y: f32, max_y: f32, width: usize, height: usize, iters: u32) -> Vec<u32> {
-    let block_size = f32s::lanes();
-
-    assert_eq!(
-        width % block_size,
-        0,
-        "image width = {} is not divisible by the number of vector lanes = {}",
-        width,
-        block_size,
-    );
-
-    let width_in_blocks = width / block_size;
-
-    // The initial X values are the same for every row.
-    let xs = unsafe {
+/// Lays out the per-column X values shared by every row, packed into SIMD
+/// blocks of `f32s::lanes()` columns each.
+fn row_xs(min_x: f32, max_x: f32, width: usize, width_in_blocks: usize) -> Vec<f32s> {
+    unsafe {
         let dx = (max_x - min_x) / (width as f32);
         let mut buf: Vec<f32s> = vec![f32s::splat(0.); width_in_blocks];
 
@@ -125,31 +208,96 @@ pub fn generate(min_x: f32, max_x: f32, min_y: f32, max_y: f32, width: usize, he
             });
 
         buf
-    };
+    }
+}
+
+pub fn generate(min_x: f32, max_x: f32, min_y: f32, max_y: f32, width: usize, height: usize, iters: u32) -> Vec<u32> {
+    let block_size = f32s::lanes();
+    // Round up to a whole number of SIMD blocks; the padding columns are
+    // computed same as any other pixel and trimmed off on the way out, so
+    // callers are no longer required to pick a width that's already a
+    // multiple of the lane count.
+    let width_in_blocks = (width + block_size - 1) / block_size;
+
+    // The initial X values are the same for every row.
+    let xs = row_xs(min_x, max_x, width, width_in_blocks);
 
     let dy = (max_y - min_y) / (height as f32);
 
-    let len = width_in_blocks * height;
-    let mut out = Vec::with_capacity(len);
-    unsafe {
-        out.set_len(len);
-    }
+    // Tile over both rows and SIMD-block columns: whole-row chunks still
+    // let one row that grazes the cardioid/bulb boundary dominate its
+    // thread, since the interior check (see `MandelbrotIter::count`) makes
+    // most of a row's cost depend on exactly how much boundary it crosses.
+    generate_tiled::<f32s, u32s, u32>(
+        width,
+        height,
+        block_size,
+        TILE_ROWS,
+        TILE_COLS_BLOCKS,
+        &xs,
+        |row| f32s::splat(min_y + dy * (row as f32)),
+        |x, y| {
+            let z = Complex { real: x, imag: y };
+            let interior = z.interior();
+            MandelbrotIter::new(z, z).count(iters, interior)
+        },
+    )
+}
+
+/// Smooth-coloring counterpart to [`generate`]: instead of the raw
+/// iteration count, each pixel gets the normalized escape value `mu`, which
+/// varies continuously across the boundary instead of banding at integer
+/// iteration counts. Pixels that never escape are reported as `iters`.
+pub fn generate_smooth(min_x: f32, max_x: f32, min_y: f32, max_y: f32, width: usize, height: usize, iters: u32) -> Vec<f32> {
+    let block_size = f32s::lanes();
+    let width_in_blocks = (width + block_size - 1) / block_size;
+
+    let xs = row_xs(min_x, max_x, width, width_in_blocks);
+    let dy = (max_y - min_y) / (height as f32);
 
-    out.par_chunks_mut(width_in_blocks).enumerate().for_each(|(i, row)| {
-        let y = f32s::splat(min_y + dy * (i as f32));
-        row.iter_mut().enumerate().for_each(|(j, count)| {
-            let x = xs[j];
+    generate_tiled::<f32s, f32s, f32>(
+        width,
+        height,
+        block_size,
+        TILE_ROWS,
+        TILE_COLS_BLOCKS,
+        &xs,
+        |row| f32s::splat(min_y + dy * (row as f32)),
+        |x, y| {
             let z = Complex { real: x, imag: y };
-            *count = MandelbrotIter::new(z).count(iters);
-        });
-    });
+            let interior = z.interior();
+            MandelbrotIter::new(z, z).count_smooth(iters, interior)
+        },
+    )
+}
 
-    // This is safe, we're transmuting from a more-aligned type to a
-    // less-aligned one.
-    #[allow(clippy::unsound_collection_transmute)]
-    unsafe {
-        let mut out: Vec<u32> = std::mem::transmute(out);
-        out.set_len(width * height);
-        out
-    }
+/// Julia-set counterpart to [`generate`]: instead of deriving `c` from the
+/// pixel grid, `c` is the fixed `(c_re, c_im)` constant for every pixel and
+/// the pixel grid supplies `z0` instead. Reuses the exact same divergence
+/// machinery as the Mandelbrot path; only where `c` comes from changes.
+pub fn generate_julia(c_re: f32, c_im: f32, min_x: f32, max_x: f32, min_y: f32, max_y: f32, width: usize, height: usize, iters: u32) -> Vec<u32> {
+    let block_size = f32s::lanes();
+    let width_in_blocks = (width + block_size - 1) / block_size;
+
+    let xs = row_xs(min_x, max_x, width, width_in_blocks);
+    let dy = (max_y - min_y) / (height as f32);
+    let c = Complex { real: f32s::splat(c_re), imag: f32s::splat(c_im) };
+
+    generate_tiled::<f32s, u32s, u32>(
+        width,
+        height,
+        block_size,
+        TILE_ROWS,
+        TILE_COLS_BLOCKS,
+        &xs,
+        |row| f32s::splat(min_y + dy * (row as f32)),
+        |x, y| {
+            let z0 = Complex { real: x, imag: y };
+            // The cardioid/bulb short-circuit tests membership of `c`, not
+            // `z0`, so it doesn't apply here: every pixel shares the same
+            // `c`, and a fixed `c` inside the cardioid says nothing about
+            // an arbitrary z0's orbit.
+            MandelbrotIter::new(z0, c).count(iters, m32s::splat(false))
+        },
+    )
 }
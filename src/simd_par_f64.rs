@@ -0,0 +1,183 @@
+//! Vectorized parallel Mandelbrot implementation using `f64` lanes.
+//!
+//! This mirrors [`crate::simd_par`] lane-for-lane, but keeps the full `f64`
+//! mantissa through the iteration instead of the `f32` cast performed by
+//! the default fast path. Use this when `(max_x - min_x) / width` gets
+//! small enough that `f32` can no longer resolve adjacent pixels (deep
+//! zooms).
+#![allow(non_camel_case_types)]
+
+use crate::*;
+use crate::tiling::generate_tiled;
+use packed_simd::*;
+
+// Tile size for `generate_f64`, in SIMD blocks.
+const TILE_ROWS: usize = 32;
+const TILE_COLS_BLOCKS: usize = 32;
+
+type u64s = u64x4;
+type f64s = f64x4;
+type m64s = m64x4;
+
+/// Storage for complex numbers in SIMD format.
+/// The real and imaginary parts are kept in separate registers.
+#[derive(Copy, Clone)]
+struct Complex {
+    real: f64s,
+    imag: f64s,
+}
+
+// Kept aligned with the scalar kernel's bailout radius (see
+// `crate::mandelbrot_kernel`); this path never computes the smooth escape
+// value, so there's no log-stability reason to raise it.
+const THRESHOLD: f64 = 4.0;
+
+impl Complex {
+    /// Returns a mask describing which members of the Mandelbrot sequence
+    /// haven't diverged yet
+    #[inline]
+    fn undiverged(&self) -> m64s {
+        let Self { real: x, imag: y } = *self;
+
+        let xx = x * x;
+        let yy = y * y;
+        let sum = xx + yy;
+
+        sum.le(f64s::splat(THRESHOLD))
+    }
+
+    /// Returns a mask describing which lanes lie in the main cardioid or
+    /// the period-2 bulb, where the sequence is known never to diverge.
+    #[inline]
+    fn interior(&self) -> m64s {
+        let Self { real: x, imag: y } = *self;
+
+        let xq = x - f64s::splat(0.25);
+        let q = xq * xq + y * y;
+        let in_cardioid = (q * (q + xq)).le(f64s::splat(0.25) * y * y);
+
+        let x1 = x + f64s::splat(1.0);
+        let in_bulb = (x1 * x1 + y * y).le(f64s::splat(0.0625));
+
+        in_cardioid | in_bulb
+    }
+}
+
+/// Mandelbrot sequence iterator using SIMD, `f64` precision.
+struct MandelbrotIter {
+    /// Initial value which generated this sequence
+    start: Complex,
+    /// Current iteration value
+    current: Complex,
+}
+
+impl MandelbrotIter {
+    /// Creates a new Mandelbrot sequence iterator for a given starting point
+    fn new(start: Complex) -> Self {
+        Self { start, current: start }
+    }
+
+    /// Returns the number of iterations it takes for each member of the
+    /// Mandelbrot sequence to diverge at this point, or `iters` if they
+    /// don't diverge.
+    ///
+    /// This function will operate on N complex numbers at once, where N is the
+    /// number of lanes in a SIMD vector of doubles.
+    fn count(mut self, iters: u32) -> u32x4 {
+        // Most of the black interior never escapes, so test membership in
+        // the main cardioid and the period-2 bulb up front: those lanes are
+        // seeded with `iters` and excluded from the iteration below instead
+        // of running it to completion.
+        let interior = self.start.interior();
+
+        let mut z = self.start;
+        let mut count = interior.select(u64s::splat(iters as u64), u64s::splat(0));
+        let active = !interior;
+
+        for _ in 0..iters {
+            // Keep track of those lanes which haven't diverged yet. The other
+            // ones will be masked off.
+            let undiverged = z.undiverged() & active;
+
+            // Stop the iteration if they all diverged. Note that we don't do
+            // this check every iteration, since a branch
+            // misprediction can hurt more than doing some extra
+            // calculations.
+            if undiverged.none() {
+                break;
+            }
+
+            count += undiverged.select(u64s::splat(1), u64s::splat(0));
+
+            z = self.next().unwrap();
+        }
+        count.cast()
+    }
+}
+
+impl Iterator for MandelbrotIter {
+    type Item = Complex;
+
+    /// Generates the next values in the sequence
+    #[inline]
+    fn next(&mut self) -> Option<Complex> {
+        let Complex { real: c_x, imag: c_y } = self.start;
+        let Complex { real: x, imag: y } = self.current;
+
+        let xx = x * x;
+        let yy = y * y;
+        let xy = x * y;
+
+        let new_x = c_x + (xx - yy);
+        let new_y = c_y + (xy + xy);
+
+        self.current = Complex { real: new_x, imag: new_y };
+
+        Some(self.current)
+    }
+}
+
+/// Lays out the per-column X values shared by every row, packed into SIMD
+/// blocks of `f64s::lanes()` columns each.
+fn row_xs(min_x: f64, max_x: f64, width: usize, width_in_blocks: usize) -> Vec<f64s> {
+    unsafe {
+        let dx = (max_x - min_x) / (width as f64);
+        let mut buf: Vec<f64s> = vec![f64s::splat(0.); width_in_blocks];
+
+        std::slice::from_raw_parts_mut(buf.as_mut_ptr() as *mut f64, width)
+            .iter_mut()
+            .enumerate()
+            .for_each(|(j, x)| {
+                *x = min_x + dx * (j as f64);
+            });
+
+        buf
+    }
+}
+
+/// `f64`-precision counterpart to [`crate::simd_par::generate`]. Trades
+/// throughput (half the lanes per vector) for the extra mantissa bits
+/// needed once the pixel pitch drops below `f32` resolution.
+pub fn generate_f64(min_x: f64, max_x: f64, min_y: f64, max_y: f64, width: usize, height: usize, iters: u32) -> Vec<u32> {
+    let block_size = f64s::lanes();
+    let width_in_blocks = (width + block_size - 1) / block_size;
+
+    // The initial X values are the same for every row.
+    let xs = row_xs(min_x, max_x, width, width_in_blocks);
+
+    let dy = (max_y - min_y) / (height as f64);
+
+    generate_tiled::<f64s, u32x4, u32>(
+        width,
+        height,
+        block_size,
+        TILE_ROWS,
+        TILE_COLS_BLOCKS,
+        &xs,
+        |row| f64s::splat(min_y + dy * (row as f64)),
+        |x, y| {
+            let z = Complex { real: x, imag: y };
+            MandelbrotIter::new(z).count(iters)
+        },
+    )
+}
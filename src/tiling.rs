@@ -0,0 +1,168 @@
+//! Shared tile decomposition used by the parallel generators.
+//!
+//! The interior tests in the scalar and SIMD kernels mean a pixel's cost
+//! varies a lot depending on how close it is to the cardioid/bulb
+//! boundary. Splitting the image into whole rows isn't fine-grained enough
+//! for rayon's work-stealing scheduler to even that out when a single row
+//! only grazes the boundary for part of its width, so tiles are
+//! rectangular: bounded in both rows and columns.
+
+use rayon::prelude::*;
+
+/// Splits a `rows x cols` grid into `(row_start, row_end, col_start,
+/// col_end)` tiles of at most `tile_rows x tile_cols`. Tiles on the
+/// trailing edge of either axis are shrunk to fit, so `rows`/`cols` need
+/// not be multiples of the tile size.
+pub(crate) fn tiles(rows: usize, cols: usize, tile_rows: usize, tile_cols: usize) -> Vec<(usize, usize, usize, usize)> {
+    (0..rows.max(1))
+        .step_by(tile_rows)
+        .flat_map(|row0| {
+            let row1 = (row0 + tile_rows).min(rows);
+            (0..cols.max(1)).step_by(tile_cols).map(move |col0| {
+                let col1 = (col0 + tile_cols).min(cols);
+                (row0, row1, col0, col1)
+            })
+        })
+        .collect()
+}
+
+/// Wraps a raw pointer so it can be handed to rayon tasks that are
+/// statically known to touch disjoint elements of the pointee (e.g. one
+/// per tile from [`tiles`]). Neither `Send` nor `Sync` can be derived for
+/// a raw pointer, so callers must uphold that disjointness themselves.
+#[derive(Clone, Copy)]
+pub(crate) struct TileBuf<T>(pub *mut T);
+
+unsafe impl<T> Send for TileBuf<T> {}
+unsafe impl<T> Sync for TileBuf<T> {}
+
+/// Trims each row of a row-major buffer from `padded_width` down to
+/// `width` columns. Used to undo the lane-count padding a SIMD generator
+/// adds internally so it can accept widths that aren't a multiple of the
+/// vector width.
+pub(crate) fn trim_padded_rows<T: Copy>(padded: &[T], padded_width: usize, width: usize, height: usize) -> Vec<T> {
+    if padded_width == width {
+        return padded.to_vec();
+    }
+
+    let mut out = Vec::with_capacity(width * height);
+    for row in 0..height {
+        out.extend_from_slice(&padded[row * padded_width..row * padded_width + width]);
+    }
+    out
+}
+
+/// Shared tile-fill loop for every SIMD `generate*` entry point, across
+/// precisions: lays out a `xs.len() x height` grid of blocks, dispatches one
+/// `pixel(x, y)` call per block tiled across rayon workers per [`tiles`],
+/// then flattens the blocks down to scalar lanes and trims off the
+/// lane-count padding via [`trim_padded_rows`].
+///
+/// `T` is the per-block SIMD vector `pixel` returns (e.g. `u32x8`) and `S`
+/// is its scalar lane type (e.g. `u32`) with `lanes` lanes per block;
+/// callers must get `T`/`S`/`lanes` to agree, since that's what makes
+/// reinterpreting the block buffer as a scalar one below sound.
+pub(crate) fn generate_tiled<V, T, S>(
+    width: usize,
+    height: usize,
+    lanes: usize,
+    tile_rows: usize,
+    tile_cols_blocks: usize,
+    xs: &[V],
+    y_at: impl Fn(usize) -> V + Sync,
+    pixel: impl Fn(V, V) -> T + Sync,
+) -> Vec<S>
+where
+    V: Copy,
+    T: Copy,
+    S: Copy,
+{
+    let width_in_blocks = xs.len();
+    let padded_width = width_in_blocks * lanes;
+
+    let len = width_in_blocks * height;
+    let mut out: Vec<T> = Vec::with_capacity(len);
+    unsafe {
+        out.set_len(len);
+    }
+
+    let buf = TileBuf(out.as_mut_ptr());
+    tiles(height, width_in_blocks, tile_rows, tile_cols_blocks)
+        .into_par_iter()
+        .for_each(|(row0, row1, col0, col1)| {
+            let buf = buf;
+            for row in row0..row1 {
+                let y = y_at(row);
+                for col in col0..col1 {
+                    let x = xs[col];
+                    let value = pixel(x, y);
+                    // Safe: tiles partition the image, so each tile owns a
+                    // disjoint set of indices.
+                    unsafe {
+                        *buf.0.add(row * width_in_blocks + col) = value;
+                    }
+                }
+            }
+        });
+
+    // This is safe, we're transmuting from a more-aligned type to a
+    // less-aligned one.
+    #[allow(clippy::unsound_collection_transmute)]
+    let out: Vec<S> = unsafe {
+        let mut out: Vec<S> = std::mem::transmute(out);
+        out.set_len(padded_width * height);
+        out
+    };
+
+    trim_padded_rows(&out, padded_width, width, height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tiles_covers_grid_exactly_once() {
+        let rows = 10;
+        let cols = 7;
+        let mut covered = vec![vec![false; cols]; rows];
+
+        for (row0, row1, col0, col1) in tiles(rows, cols, 3, 4) {
+            for row in row0..row1 {
+                for col in col0..col1 {
+                    assert!(!covered[row][col], "cell ({row}, {col}) covered twice");
+                    covered[row][col] = true;
+                }
+            }
+        }
+
+        assert!(covered.iter().all(|row| row.iter().all(|&c| c)), "some cell never covered");
+    }
+
+    #[test]
+    fn tiles_shrinks_trailing_edge() {
+        // 10 rows / tile_rows 3 leaves a trailing tile of height 1, and the
+        // 7-wide column split against tile_cols 4 one of width 3.
+        let got = tiles(10, 7, 3, 4);
+        assert_eq!(got.last(), Some(&(9, 10, 4, 7)));
+    }
+
+    #[test]
+    fn tiles_handles_empty_grid() {
+        assert_eq!(tiles(0, 0, 3, 4), vec![(0, 0, 0, 0)]);
+    }
+
+    #[test]
+    fn trim_padded_rows_is_noop_when_widths_match() {
+        let data = vec![1, 2, 3, 4, 5, 6];
+        assert_eq!(trim_padded_rows(&data, 3, 3, 2), data);
+    }
+
+    #[test]
+    fn trim_padded_rows_drops_trailing_padding_per_row() {
+        // 2 rows, padded to 4 columns, real width 3: the 4th column of each
+        // row is padding and should be dropped.
+        let padded = vec![1, 2, 3, 0, 4, 5, 6, 0];
+        assert_eq!(trim_padded_rows(&padded, 4, 3, 2), vec![1, 2, 3, 4, 5, 6]);
+    }
+}